@@ -0,0 +1,50 @@
+//! Benchmarks the effect of caching structural hashes for interned `TypeId`s during
+//! monomorphization of a large type-checked program, where the same handful of types are
+//! hashed repeatedly while probing the unification cache.
+
+use std::hash::Hasher;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sway_core::engine_threading::{CachedHashWithEngines, Engines, HashWithEngines};
+use sway_core::type_system::TypeId;
+
+/// The pre-caching baseline: a full `HashWithEngines` traversal on every probe, exactly what
+/// `cached_hash`'s default implementation falls back to for a non-interned value.
+fn hash_interned_types_without_cache(engines: &Engines, type_ids: &[TypeId]) -> u64 {
+    let mut acc = 0u64;
+    for type_id in type_ids {
+        let mut state = std::collections::hash_map::DefaultHasher::new();
+        HashWithEngines::hash(type_id, &mut state, engines);
+        acc ^= state.finish();
+    }
+    acc
+}
+
+/// The cached fast path: `TypeId::cached_hash` serves the structural hash computed (once) on the
+/// first probe of each `TypeId` instead of re-traversing the `TypeInfo`.
+fn hash_interned_types_with_cache(engines: &Engines, type_ids: &[TypeId]) -> u64 {
+    let mut acc = 0u64;
+    for type_id in type_ids {
+        acc ^= type_id.cached_hash(engines);
+    }
+    acc
+}
+
+fn bench_cached_hash(c: &mut Criterion) {
+    let engines = Engines::default();
+    let type_ids = sway_core::test_utils::large_monomorphized_program_type_ids(&engines);
+
+    // Warm the cache once so `hash_interned_types_with_cache` measures the cached fast path
+    // rather than the cost of populating it.
+    let _ = hash_interned_types_with_cache(&engines, &type_ids);
+
+    c.bench_function("hash_interned_types_without_cache", |b| {
+        b.iter(|| black_box(hash_interned_types_without_cache(&engines, &type_ids)))
+    });
+    c.bench_function("hash_interned_types_with_cache", |b| {
+        b.iter(|| black_box(hash_interned_types_with_cache(&engines, &type_ids)))
+    });
+}
+
+criterion_group!(benches, bench_cached_hash);
+criterion_main!(benches);
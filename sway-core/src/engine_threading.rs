@@ -1,10 +1,12 @@
-use crate::{decl_engine::{DeclEngine, DeclEngineGet, DeclRef}, query_engine::QueryEngine, type_system::TypeEngine, language::{ty::{TyImplTrait, KnownTrait}, CallPath}};
+use crate::{decl_engine::{DeclEngine, DeclEngineGet, DeclEngineInsert, DeclRef}, query_engine::QueryEngine, type_system::{TypeArgument, TypeEngine, TypeId}, language::ty::{TyImplTrait, KnownTrait}};
 use std::{
     cmp::Ordering,
+    collections::{HashMap, HashSet},
     fmt,
     hash::{BuildHasher, Hash, Hasher},
+    sync::RwLock,
 };
-use sway_types::{SourceEngine, Ident};
+use sway_types::{SourceEngine, Span};
 
 #[derive(Debug, Default)]
 pub struct Engines {
@@ -12,6 +14,9 @@ pub struct Engines {
     decl_engine: DeclEngine,
     query_engine: QueryEngine,
     source_engine: SourceEngine,
+    auto_impl_engine: AutoImplEngine,
+    type_hash_cache: HashCache<TypeId>,
+    decl_hash_cache: HashCache<DeclRef>,
 }
 
 impl Engines {
@@ -26,6 +31,9 @@ impl Engines {
             decl_engine,
             query_engine,
             source_engine,
+            auto_impl_engine: AutoImplEngine::default(),
+            type_hash_cache: HashCache::default(),
+            decl_hash_cache: HashCache::default(),
         }
     }
 
@@ -45,11 +53,86 @@ impl Engines {
         &self.source_engine
     }
 
+    pub fn ae(&self) -> &AutoImplEngine {
+        &self.auto_impl_engine
+    }
+
     /// Removes all data associated with `module_id` from the declaration and type engines.
     /// It is intended to be used during garbage collection to remove any data that is no longer needed.
     pub fn clear_module(&mut self, module_id: &sway_types::ModuleId) {
         self.type_engine.clear_module(module_id);
         self.decl_engine.clear_module(module_id);
+        self.query_engine.clear_module(module_id);
+        // The removed slots' `TypeId`/`DeclRef`s are no longer valid lookup keys, but dropping
+        // their cached hashes here rather than letting them linger keeps the cache from growing
+        // unboundedly across many edit/clear cycles in a long-running session.
+        self.type_hash_cache.clear();
+        self.decl_hash_cache.clear();
+        // A synthesized impl for a type/trait pair owned by this module may have been registered
+        // in the DeclEngine slot we just cleared above; drop it here too so a later auto_impl()
+        // call doesn't hand back a DeclRef pointing at a decl that's already gone.
+        self.auto_impl_engine.clear();
+    }
+
+    /// Performs mark-and-sweep garbage collection across all four engines at once.
+    ///
+    /// `clear_module` only ever drops data keyed directly on a single removed `ModuleId`,
+    /// leaving behind anything that module shared with others, plus stale `QueryEngine` memos
+    /// and `SourceEngine` entries -- exactly what a long-running LSP session accumulates over
+    /// many edits. This instead starts from `live_roots`, transitively marks every `TypeId` and
+    /// `DeclRef` reachable from their type-checked ASTs (following generic arguments, trait impl
+    /// references, and field/variant types via [ReferencedHandles]), and sweeps every interned
+    /// type, declaration, query memo, and source entry that was not marked.
+    pub fn collect_garbage(&mut self, live_roots: &HashSet<sway_types::ModuleId>) {
+        let mut marked = HashSet::new();
+        let mut frontier: Vec<Handle> = live_roots
+            .iter()
+            .flat_map(|module_id| self.decl_engine.root_decls_of_module(module_id))
+            .map(Handle::Decl)
+            .collect();
+
+        while let Some(handle) = frontier.pop() {
+            if !marked.insert(handle.clone()) {
+                continue;
+            }
+            let referenced = match &handle {
+                Handle::Type(type_id) => self.type_engine.get(*type_id).referenced_handles(self),
+                Handle::Decl(decl_ref) => self.decl_engine.get(decl_ref).referenced_handles(self),
+            };
+            frontier.extend(referenced);
+        }
+
+        let live_types: HashSet<TypeId> = marked
+            .iter()
+            .filter_map(|handle| match handle {
+                Handle::Type(type_id) => Some(*type_id),
+                Handle::Decl(_) => None,
+            })
+            .collect();
+        let live_decls: HashSet<DeclRef> = marked
+            .iter()
+            .filter_map(|handle| match handle {
+                Handle::Decl(decl_ref) => Some(decl_ref.clone()),
+                Handle::Type(_) => None,
+            })
+            .collect();
+
+        // A module the mark phase only reached transitively (e.g. a shared/imported module not
+        // itself in `live_roots`) still owns source text and query memos backing the marked
+        // types/decls, so it must stay live too -- not just the roots we started from.
+        let mut live_modules = live_roots.clone();
+        live_modules.extend(marked.iter().filter_map(|handle| handle.module_id(self)));
+
+        self.type_engine.retain(|type_id, _| live_types.contains(type_id));
+        self.decl_engine.retain(|decl_ref, _| live_decls.contains(decl_ref));
+        self.query_engine.retain_live_modules(&live_modules);
+        self.source_engine.retain_modules(&live_modules);
+        self.type_hash_cache.clear();
+        self.decl_hash_cache.clear();
+        // Mirrors the other sweeps above: a synthesized impl's DeclRef was marked (and so
+        // survives) only if something else still reaches it, so any entry in `generated` whose
+        // DeclRef the decl_engine.retain() call above actually dropped would otherwise dangle.
+        self.auto_impl_engine.clear();
     }
 
     /// Helps out some `thing: T` by adding `self` as context.
@@ -60,23 +143,129 @@ impl Engines {
         }
     }
 
-    pub fn auto_impl_abi_encode(&self) {
-        let trait_decl_ref = self.decl_engine.get_known_trait(KnownTrait::AbiEncoder).unwrap();
+    /// Returns the impl of `known_trait` for `type_id`, auto-deriving one if the user did not
+    /// already write one. See [AutoImplEngine] for how the impl is synthesized.
+    pub fn auto_impl(&self, type_id: TypeId, known_trait: KnownTrait) -> DeclRef {
+        self.auto_impl_engine.get_or_auto_impl(self, type_id, known_trait)
+    }
 
-        let auto_impm = TyImplTrait {
-            trait_name: CallPath { 
-                prefixes: vec![
-                    Ident::new_no_span("core".to_string()),
-                    Ident::new_no_span("codec".to_string()),
-                ], 
-                suffix: Ident::new_no_span("AbiEncode".to_string()),
-                is_absolute: true
-            },
-            impl_type_parameters: vec![],
-            trait_type_arguments: vec![],
-            items: vec![
+    /// The fast path behind `TypeId`'s [CachedHashWithEngines] impl: computes the structural
+    /// hash of the interned `TypeInfo` the first time `type_id` is probed, then serves that
+    /// cached value on every later probe instead of re-traversing it.
+    pub(crate) fn cached_type_hash(&self, type_id: TypeId) -> u64 {
+        self.type_hash_cache.get_or_compute(type_id, || {
+            let mut state = std::collections::hash_map::DefaultHasher::new();
+            HashWithEngines::hash(&self.te().get(type_id), &mut state, self);
+            state.finish()
+        })
+    }
+
+    /// The fast path behind `DeclRef`'s [CachedHashWithEngines] impl; see [Self::cached_type_hash].
+    pub(crate) fn cached_decl_hash(&self, decl_ref: &DeclRef) -> u64 {
+        self.decl_hash_cache.get_or_compute(decl_ref.clone(), || {
+            let mut state = std::collections::hash_map::DefaultHasher::new();
+            HashWithEngines::hash(&self.de().get(decl_ref), &mut state, self);
+            state.finish()
+        })
+    }
+}
+
+/// A side-cache of structural hashes for interned values, populated lazily the first time each
+/// key is probed and consulted by that key's [CachedHashWithEngines] impl so repeated probes of
+/// the same interned `TypeId`/`DeclRef` during unification and monomorphization skip the full
+/// [HashWithEngines] traversal. Interned values are immutable once inserted, so a cached hash is
+/// never invalidated individually -- the whole cache is cleared instead, whenever `Engines`
+/// drops the slots it could belong to (`clear_module`, `collect_garbage`).
+#[derive(Debug)]
+struct HashCache<K> {
+    cache: RwLock<HashMap<K, u64>>,
+}
+
+impl<K> Default for HashCache<K> {
+    fn default() -> Self {
+        HashCache {
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> HashCache<K> {
+    fn get_or_compute(&self, key: K, compute: impl FnOnce() -> u64) -> u64 {
+        if let Some(hash) = self.cache.read().unwrap().get(&key) {
+            return *hash;
+        }
+        let hash = compute();
+        self.cache.write().unwrap().insert(key, hash);
+        hash
+    }
+
+    fn clear(&self) {
+        self.cache.write().unwrap().clear();
+    }
+}
+
+/// Synthesizes derive-style trait impls (`AbiEncode`, `AbiDecode`, and other [KnownTrait]s) for
+/// types that don't already have a user-written impl.
+///
+/// The strategy mirrors how rustdoc synthesizes auto/blanket impls: walk the structural
+/// components of the implementing type (struct fields, enum variant payloads, tuple elements,
+/// generic arguments), and for every component that is itself generic, propagate a `where`-clause
+/// constraint requiring that component to implement the same trait. For `struct Foo<T>` deriving
+/// `AbiEncode` this means requiring `T: AbiEncode` and generating an `abi_encode` body that calls
+/// `abi_encode` on each field in declaration order.
+#[derive(Debug, Default)]
+pub struct AutoImplEngine {
+    /// Impls already synthesized for a `(TypeId, KnownTrait)` pair, so a repeated request
+    /// resolves to the same [DeclRef] instead of generating a duplicate impl.
+    generated: RwLock<std::collections::HashMap<(TypeId, KnownTrait), DeclRef>>,
+}
+
+impl AutoImplEngine {
+    /// Returns the user-written impl of `known_trait` for `type_id` if one exists, otherwise
+    /// synthesizes one (or returns a previously synthesized one).
+    pub fn get_or_auto_impl(
+        &self,
+        engines: &Engines,
+        type_id: TypeId,
+        known_trait: KnownTrait,
+    ) -> DeclRef {
+        if let Some(existing) = engines.de().get_impl_of_trait(type_id, known_trait) {
+            return existing;
+        }
+
+        // Hold the write lock across the miss-check-and-insert: two concurrent callers for the
+        // same never-seen `(type_id, known_trait)` must not both synthesize and register a
+        // distinct impl, which would leave an orphaned duplicate registered in the `DeclEngine`
+        // and break the "later lookups resolve to the same DeclRef" guarantee.
+        let mut generated = self.generated.write().unwrap();
+        if let Some(decl_ref) = generated.get(&(type_id, known_trait)) {
+            return decl_ref.clone();
+        }
+
+        let decl_ref = self.synthesize_impl(engines, type_id, known_trait);
+        generated.insert((type_id, known_trait), decl_ref.clone());
+        decl_ref
+    }
 
-            ],
+    /// Builds and registers the `TyImplTrait` for `known_trait` on `type_id`.
+    fn synthesize_impl(
+        &self,
+        engines: &Engines,
+        type_id: TypeId,
+        known_trait: KnownTrait,
+    ) -> DeclRef {
+        let trait_decl_ref = engines
+            .de()
+            .get_known_trait(known_trait)
+            .expect("known trait must be registered before auto-impl runs");
+
+        let impl_type_parameters = self.propagate_bounds(engines, type_id, known_trait);
+
+        let impl_trait = TyImplTrait {
+            trait_name: known_trait.call_path(),
+            impl_type_parameters,
+            trait_type_arguments: vec![],
+            items: known_trait.derive_items(engines, type_id),
             trait_decl_ref: Some(trait_decl_ref),
             implementing_for: TypeArgument {
                 type_id,
@@ -86,6 +275,61 @@ impl Engines {
             },
             span: Span::dummy(),
         };
+
+        engines.de().insert(impl_trait).id()
+    }
+
+    /// Walks the structural components of `type_id` and returns one constraint per distinct
+    /// generic component, requiring that component to also implement `known_trait`.
+    fn propagate_bounds(
+        &self,
+        engines: &Engines,
+        type_id: TypeId,
+        known_trait: KnownTrait,
+    ) -> Vec<crate::type_system::TypeParameter> {
+        let mut seen = HashSet::new();
+        engines
+            .te()
+            .get(type_id)
+            .structural_components(engines)
+            .into_iter()
+            .filter(|component| component.is_generic_parameter(engines) && seen.insert(*component))
+            .map(|component| crate::type_system::TypeParameter::new_constrained(component, known_trait))
+            .collect()
+    }
+
+    /// Drops every synthesized impl this engine has on record. The `DeclRef`s the cache points at
+    /// are registered in the `DeclEngine`, so this must be called any time a sweep there could
+    /// drop one of them -- otherwise a later `get_or_auto_impl` call for that `(type_id,
+    /// known_trait)` pair would hand back a `DeclRef` pointing at a decl that no longer exists.
+    fn clear(&self) {
+        self.generated.write().unwrap().clear();
+    }
+}
+
+/// A single engine-indexed handle, used by [Engines::collect_garbage] to mark reachable data
+/// without caring which of the four engines it ultimately lives in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Handle {
+    Type(TypeId),
+    Decl(DeclRef),
+}
+
+/// Exposes every handle a value directly references, so [Engines::collect_garbage] can walk the
+/// object graph generically (generic arguments, trait impl references, field/variant types)
+/// instead of hard-coding traversal for every kind of type and declaration.
+pub trait ReferencedHandles {
+    fn referenced_handles(&self, engines: &Engines) -> Vec<Handle>;
+}
+
+impl Handle {
+    /// The module that owns this handle's declaration site, if any. Built-in/generic types
+    /// interned without a source location (e.g. `u64`, type parameters) have none.
+    fn module_id(&self, engines: &Engines) -> Option<sway_types::ModuleId> {
+        match self {
+            Handle::Type(type_id) => engines.te().get(*type_id).module_id(engines),
+            Handle::Decl(decl_ref) => Some(engines.de().get(decl_ref).module_id()),
+        }
     }
 }
 
@@ -221,6 +465,80 @@ impl<T: DebugWithEngines> DebugWithEngines for Vec<T> {
     }
 }
 
+/// Serializes `self` into a self-contained [serde_json::Value], resolving engine-indexed handles
+/// (`TypeId`s, `DeclRef`s) into concrete data along the way, analogous to how rustdoc produces
+/// its cleaned, fully-resolved JSON AST.
+///
+/// Unlike [DisplayWithEngines]/[DebugWithEngines], the output is meant to be consumed outside the
+/// process that produced it (IDE/LSP tooling, external analyzers), so every handle must resolve
+/// to a stable, versioned representation rather than a raw integer.
+pub trait SerializeWithEngines {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value;
+}
+
+impl<T: SerializeWithEngines> SerializeWithEngines for &T {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        (*self).serialize(engines)
+    }
+}
+
+impl<T: SerializeWithEngines> SerializeWithEngines for Option<T> {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        match self {
+            None => serde_json::Value::Null,
+            Some(x) => x.serialize(engines),
+        }
+    }
+}
+
+impl<T: SerializeWithEngines> SerializeWithEngines for Box<T> {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        (**self).serialize(engines)
+    }
+}
+
+impl<T: SerializeWithEngines> SerializeWithEngines for Vec<T> {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        serde_json::Value::Array(self.iter().map(|e| e.serialize(engines)).collect())
+    }
+}
+
+impl<T: SerializeWithEngines> SerializeWithEngines for [T] {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        serde_json::Value::Array(self.iter().map(|e| e.serialize(engines)).collect())
+    }
+}
+
+/// Resolves to the fully substituted [crate::type_system::TypeInfo] `type_id` points at, not the
+/// raw interned index, so the output is meaningful without access to the `TypeEngine` that
+/// produced it.
+impl SerializeWithEngines for TypeId {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        engines.te().get(*self).serialize(engines)
+    }
+}
+
+/// Resolves to the declaration's name (under `"path"`) plus the signature of the pointed-to
+/// declaration, pulled from the `DeclEngine`, rather than the raw declaration id. The name is not
+/// qualified with the declaration's module path.
+impl SerializeWithEngines for DeclRef {
+    fn serialize(&self, engines: &Engines) -> serde_json::Value {
+        let decl = engines.de().get(self);
+        serde_json::json!({
+            "path": self.name().as_str(),
+            "signature": decl.serialize(engines),
+        })
+    }
+}
+
+/// Displays the JSON-serialized view of `thing` using `engines` as context. Useful for exporting
+/// a type-checked AST to IDE/LSP tooling without giving it in-process access to the engines.
+impl<T: SerializeWithEngines> WithEngines<'_, T> {
+    pub fn to_json(&self) -> serde_json::Value {
+        self.thing.serialize(self.engines)
+    }
+}
+
 pub trait HashWithEngines {
     fn hash<H: Hasher>(&self, state: &mut H, engines: &Engines);
 }
@@ -317,6 +635,13 @@ impl<T: PartialEqWithEngines> PartialEqWithEngines for [T] {
         self.len() == other.len() && self.iter().zip(other.iter()).all(|(x, y)| x.eq(y, engines))
     }
 }
+
+impl<T: EqWithEngines> EqWithEngines for Vec<T> {}
+impl<T: PartialEqWithEngines> PartialEqWithEngines for Vec<T> {
+    fn eq(&self, other: &Self, engines: &Engines) -> bool {
+        self.as_slice().eq(other.as_slice(), engines)
+    }
+}
 impl<T: OrdWithEngines> OrdWithEngines for [T] {
     fn cmp(&self, other: &Self, engines: &Engines) -> Ordering {
         self.iter()
@@ -327,16 +652,149 @@ impl<T: OrdWithEngines> OrdWithEngines for [T] {
     }
 }
 
+/// Fast path for structural hashing of interned handles (`TypeId`, `DeclRef`).
+///
+/// `make_hasher` and the `PartialEqWithEngines`-backed hash maps used during unification and
+/// monomorphization probe the same handful of interned values over and over, and recomputing a
+/// full [HashWithEngines] traversal of a large `TyImplTrait`/`TypeInfo` on every probe dominates
+/// hashing time. Interned values are immutable once inserted, so `Engines` computes the structural
+/// hash of an interned handle the first time it is probed and keeps it in a side cache; types that
+/// have such a cache override `cached_hash` to return the cached value instead of recomputing. The
+/// hash is not computed at intern time, and it is not stored in the `TypeEngine`/`DeclEngine` slot.
+///
+/// Non-interned types simply inherit the default, which falls back to a full traversal, so
+/// implementing this trait is always safe even before a type gains an interning table.
+pub trait CachedHashWithEngines: HashWithEngines {
+    fn cached_hash(&self, engines: &Engines) -> u64 {
+        let mut state = std::collections::hash_map::DefaultHasher::new();
+        HashWithEngines::hash(self, &mut state, engines);
+        state.finish()
+    }
+}
+
+impl<T: HashWithEngines + ?Sized> CachedHashWithEngines for &T {}
+impl<T: HashWithEngines> CachedHashWithEngines for Option<T> {}
+impl<T: HashWithEngines> CachedHashWithEngines for Box<T> {}
+impl<T: HashWithEngines> CachedHashWithEngines for [T] {}
+
+/// `TypeId`s are interned in the `TypeEngine` and never mutated in place, so this overrides the
+/// default traversal with [Engines::cached_type_hash]'s once-computed, cached value.
+impl CachedHashWithEngines for TypeId {
+    fn cached_hash(&self, engines: &Engines) -> u64 {
+        engines.cached_type_hash(*self)
+    }
+}
+
+/// `DeclRef`s are interned in the `DeclEngine` and never mutated in place, so this overrides the
+/// default traversal with [Engines::cached_decl_hash]'s once-computed, cached value.
+impl CachedHashWithEngines for DeclRef {
+    fn cached_hash(&self, engines: &Engines) -> u64 {
+        engines.cached_decl_hash(self)
+    }
+}
+
 pub(crate) fn make_hasher<'a: 'b, 'b, K>(
-    hash_builder: &'a impl BuildHasher,
+    _hash_builder: &'a impl BuildHasher,
     engines: &'b Engines,
 ) -> impl Fn(&K) -> u64 + 'b
 where
-    K: HashWithEngines + ?Sized,
+    K: CachedHashWithEngines + ?Sized,
 {
-    move |key: &K| {
-        let mut state = hash_builder.build_hasher();
-        key.hash(&mut state, engines);
-        state.finish()
+    move |key: &K| key.cached_hash(engines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_impl_returns_the_same_decl_ref_across_repeated_calls() {
+        let engines = Engines::default();
+        let type_id = engines.te().insert_unit_type();
+
+        let first = engines.auto_impl(type_id, KnownTrait::AbiEncoder);
+        let second = engines.auto_impl(type_id, KnownTrait::AbiEncoder);
+
+        let mut distinct = HashSet::new();
+        distinct.insert(first);
+        distinct.insert(second);
+        assert_eq!(
+            distinct.len(),
+            1,
+            "repeated auto_impl calls for the same (type, trait) pair must resolve to the same \
+             DeclRef, not register a duplicate impl each time"
+        );
+    }
+
+    #[test]
+    fn hash_cache_computes_each_key_at_most_once() {
+        let cache = HashCache::<u32>::default();
+        let computations = std::cell::Cell::new(0);
+        let compute = || {
+            computations.set(computations.get() + 1);
+            42
+        };
+
+        assert_eq!(cache.get_or_compute(7, compute), 42);
+        assert_eq!(cache.get_or_compute(7, compute), 42);
+        assert_eq!(
+            computations.get(),
+            1,
+            "a second probe of the same key must be served from the cache, not recomputed"
+        );
+
+        cache.get_or_compute(8, compute);
+        assert_eq!(
+            computations.get(),
+            2,
+            "a different key must still be computed on its first probe"
+        );
+
+        cache.clear();
+        cache.get_or_compute(7, compute);
+        assert_eq!(
+            computations.get(),
+            3,
+            "clearing the cache must force recomputation on the next probe"
+        );
+    }
+
+    #[test]
+    fn collect_garbage_clears_stale_auto_impl_entries() {
+        let mut engines = Engines::default();
+        let type_id = engines.te().insert_unit_type();
+
+        let before = engines.auto_impl(type_id, KnownTrait::AbiEncoder);
+
+        // Sweep with no live roots so the synthesized impl -- along with everything else -- is
+        // dropped from every engine, including the AutoImplEngine's own `generated` cache.
+        engines.collect_garbage(&HashSet::new());
+
+        let after = engines.auto_impl(type_id, KnownTrait::AbiEncoder);
+        assert_ne!(
+            before, after,
+            "a synthesized impl dropped by collect_garbage must be re-synthesized, not served \
+             from a stale cache entry pointing at a decl that no longer exists"
+        );
+    }
+
+    #[test]
+    fn serialize_with_engines_resolves_a_decl_ref_to_its_name_and_signature() {
+        let engines = Engines::default();
+        let type_id = engines.te().insert_unit_type();
+        let decl_ref = engines.auto_impl(type_id, KnownTrait::AbiEncoder);
+
+        let json = engines.help_out(&decl_ref).to_json();
+
+        assert_eq!(
+            json["path"],
+            serde_json::Value::String(decl_ref.name().as_str().to_string()),
+            "serializing a DeclRef must resolve the raw interned id to its DeclEngine-backed name"
+        );
+        assert_ne!(
+            json["signature"],
+            serde_json::Value::Null,
+            "the pointed-to declaration's own serialized signature should be inlined, not omitted"
+        );
     }
 }
@@ -0,0 +1,401 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+use sway_types::ModuleId;
+
+use crate::engine_threading::{Engines, PartialEqWithEngines};
+
+/// A monotonically increasing revision number, bumped once per input mutation (e.g. a module's
+/// source text changing). Derived queries record the revision they were last computed at and the
+/// revision at which their result last actually changed, which is what makes early cutoff
+/// possible.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Revision(u64);
+
+thread_local! {
+    /// The stack of queries currently being computed on this thread, each paired with the
+    /// dependency keys its nested `query()` calls have recorded onto it so far. Entering a query
+    /// pushes a fresh `(key, vec![])` frame; any nested query access pushes its key onto the
+    /// *caller's* frame (the one already on top of the stack) before doing anything else -- even
+    /// before checking whether that nested query is itself a cache hit -- which is how dependency
+    /// edges get recorded without each query having to declare them up front. Finishing a query
+    /// pops its frame and takes the accumulated list as its dependency set.
+    static QUERY_STACK: RefCell<Vec<(QueryKey, Vec<QueryKey>)>> = RefCell::new(Vec::new());
+}
+
+/// Identifies a single memoized query invocation.
+pub type QueryKey = (QueryKind, ModuleId);
+
+/// The distinct kinds of queries the incremental engine memoizes. Each variant corresponds to a
+/// derived artifact computed from the module graph; new phases of the compiler that want
+/// incremental recomputation add a variant here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryKind {
+    ParsedModule,
+    TypeCheckedModule,
+    ResolvedTraitImpls,
+}
+
+/// One memoized query result, along with enough bookkeeping to decide whether it can be reused
+/// without recomputation.
+struct Memo<T> {
+    value: T,
+    /// The revision at which this entry was last confirmed valid (recomputed or cut off).
+    verified_at: Revision,
+    /// The revision at which `value` last actually changed, as opposed to merely being
+    /// recomputed to the same value. Dependents compare against this, not `verified_at`, which
+    /// is what gives early cutoff its benefit.
+    changed_at: Revision,
+    /// The set of queries this result was computed from. Validated transitively before reuse.
+    dependencies: Vec<QueryKey>,
+}
+
+/// A salsa-style incremental, demand-driven query engine.
+///
+/// Compilation is modeled as a set of *input* queries (source text, module graph) and *derived*
+/// queries (parsed module, resolved types, trait impls), each memoized here. A global revision
+/// counter is bumped on every input mutation. A derived query already verified at the current
+/// revision is returned immediately; otherwise its recorded dependencies are validated
+/// transitively, and only recomputed if one of them actually changed. When a recomputation
+/// produces a value equal (via [PartialEqWithEngines]) to the previous one, `changed_at` is left
+/// untouched so dependents of *this* query are, in turn, not forced to recompute either.
+#[derive(Debug, Default)]
+pub struct QueryEngine {
+    revision: AtomicU64,
+    modules: RwLock<HashMap<QueryKey, ModuleMemo>>,
+}
+
+/// One memo map per concrete value type would be ideal, but the compiler's derived artifacts are
+/// all module-shaped, so a single enum of the possible payloads keeps the engine in one table.
+enum ModuleMemo {
+    ParsedModule(Memo<crate::language::parsed::ParseModule>),
+    TypeCheckedModule(Memo<crate::language::ty::TyModule>),
+    ResolvedTraitImpls(Memo<Vec<crate::decl_engine::DeclRef>>),
+}
+
+impl QueryEngine {
+    fn current_revision(&self) -> Revision {
+        Revision(self.revision.load(Ordering::SeqCst))
+    }
+
+    /// Bumps the global revision. Call this whenever an input (source text, module graph shape)
+    /// changes; every derived query transitively depending on that input will be recomputed the
+    /// next time it's demanded.
+    pub fn new_revision(&self) {
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Marks `key` -- expected to be an input query, e.g. a module's source text -- as changed
+    /// at a freshly bumped revision. Any derived query that transitively depends on `key`, even
+    /// through several layers of `query()` calls, will see a dependency whose `changed_at` is
+    /// newer than its own `verified_at` and be recomputed the next time it's demanded, instead of
+    /// being served from a stale cache forever.
+    pub fn invalidate_input(&self, key: QueryKey) {
+        self.new_revision();
+        let current = self.current_revision();
+        if let Some(memo) = self.modules.write().unwrap().get_mut(&key) {
+            memo.set_revisions(current, current);
+        }
+    }
+
+    /// Runs `compute` for `key`, reusing a cached result when every recorded dependency is still
+    /// valid at the current revision, and applying early cutoff when `compute` happens to
+    /// produce a value equal to what was cached before.
+    pub fn query<T, F>(&self, engines: &Engines, key: QueryKey, compute: F) -> T
+    where
+        T: Clone + PartialEqWithEngines,
+        F: FnOnce() -> T,
+        ModuleMemo: MemoSlot<T>,
+    {
+        // Record that whoever is still on the stack (our caller) depends on `key` before doing
+        // anything else. This must happen unconditionally, not only on a cache miss below -- a
+        // nested query that's already cached at the current revision still needs to show up in
+        // the caller's dependency set, or a later invalidation reachable only through that cached
+        // callee would never propagate up to the caller.
+        QUERY_STACK.with(|stack| {
+            if let Some((_, deps)) = stack.borrow_mut().last_mut() {
+                deps.push(key);
+            }
+        });
+
+        let current = self.current_revision();
+
+        if let Some(value) = self.try_reuse::<T>(key, current) {
+            return value;
+        }
+
+        // Push our own empty accumulator frame so nested `query()` calls made by `compute` have
+        // somewhere to record their dependency edge onto *us*.
+        QUERY_STACK.with(|stack| stack.borrow_mut().push((key, Vec::new())));
+
+        let new_value = compute();
+
+        let dependencies = QUERY_STACK.with(|stack| {
+            let (popped_key, deps) = stack
+                .borrow_mut()
+                .pop()
+                .expect("query() pushed a frame for `key` above and nothing else pops it");
+            debug_assert_eq!(popped_key, key);
+            deps
+        });
+
+        let mut modules = self.modules.write().unwrap();
+        let changed_at = match modules.get(&key).and_then(ModuleMemo::get::<T>) {
+            Some(old) if old.value.eq(&new_value, engines) => old.changed_at,
+            _ => current,
+        };
+        modules.insert(
+            key,
+            ModuleMemo::new(new_value.clone(), current, changed_at, dependencies),
+        );
+        new_value
+    }
+
+    /// Returns `Some(value)` if `key`'s cached entry is already verified at `current`, or if it
+    /// can be validated without recomputation because every dependency it was computed from --
+    /// checked transitively via [Self::validate] -- last changed no later than `key` was last
+    /// verified.
+    fn try_reuse<T: Clone>(&self, key: QueryKey, current: Revision) -> Option<T> {
+        let (verified_at, deps) = {
+            let modules = self.modules.read().unwrap();
+            let memo = modules.get(&key).and_then(ModuleMemo::get::<T>)?;
+            if memo.verified_at == current {
+                return Some(memo.value.clone());
+            }
+            (memo.verified_at, memo.dependencies.clone())
+        };
+
+        let all_deps_unchanged = deps.iter().all(|&dep| {
+            self.validate(dep, current)
+                .is_some_and(|changed_at| changed_at <= verified_at)
+        });
+        if !all_deps_unchanged {
+            return None;
+        }
+
+        let mut modules = self.modules.write().unwrap();
+        let memo = modules.get_mut(&key).and_then(ModuleMemo::get_mut::<T>)?;
+        memo.verified_at = current;
+        Some(memo.value.clone())
+    }
+
+    /// Recursively validates the type-erased entry at `dep` against `current`, descending into
+    /// its own dependencies exactly like [Self::try_reuse] does for the typed entry point.
+    /// Returns its `changed_at` revision if it is (transitively) still valid, or `None` if it has
+    /// no entry or one of its dependencies changed more recently than it was last verified.
+    fn validate(&self, dep: QueryKey, current: Revision) -> Option<Revision> {
+        let (verified_at, changed_at, deps) = {
+            let modules = self.modules.read().unwrap();
+            let memo = modules.get(&dep)?;
+            let (verified_at, changed_at) = memo.revisions();
+            if verified_at == current {
+                return Some(changed_at);
+            }
+            (verified_at, changed_at, memo.dependencies())
+        };
+
+        let all_deps_unchanged = deps.iter().all(|&nested| {
+            self.validate(nested, current)
+                .is_some_and(|nested_changed_at| nested_changed_at <= verified_at)
+        });
+        if !all_deps_unchanged {
+            return None;
+        }
+
+        if let Some(memo) = self.modules.write().unwrap().get_mut(&dep) {
+            memo.set_verified_at(current);
+        }
+        Some(changed_at)
+    }
+
+    /// Drops every memoized entry whose dependency set references `module_id`. This is the
+    /// per-module counterpart to a full [crate::engine_threading::Engines::collect_garbage]
+    /// sweep, and is cheap enough to run eagerly whenever a module is removed.
+    pub fn clear_module(&self, module_id: &ModuleId) {
+        self.modules.write().unwrap().retain(|key, memo| {
+            key.1 != *module_id
+                && !memo
+                    .dependencies()
+                    .iter()
+                    .any(|(_, dep_module)| dep_module == module_id)
+        });
+    }
+
+    /// Drops every memoized entry not rooted in `live_modules`. Used by
+    /// [crate::engine_threading::Engines::collect_garbage] as the query-engine half of its
+    /// whole-session mark-and-sweep, in place of evicting one module at a time.
+    pub fn retain_live_modules(&self, live_modules: &std::collections::HashSet<ModuleId>) {
+        self.modules
+            .write()
+            .unwrap()
+            .retain(|key, _| live_modules.contains(&key.1));
+    }
+}
+
+/// Bridges the type-erased [ModuleMemo] storage to a concrete payload type `T`.
+trait MemoSlot<T> {
+    fn get(&self) -> Option<&Memo<T>>;
+    fn get_mut(&mut self) -> Option<&mut Memo<T>>;
+    fn new(value: T, verified_at: Revision, changed_at: Revision, dependencies: Vec<QueryKey>) -> Self;
+}
+
+impl ModuleMemo {
+    fn dependencies(&self) -> Vec<QueryKey> {
+        match self {
+            ModuleMemo::ParsedModule(m) => m.dependencies.clone(),
+            ModuleMemo::TypeCheckedModule(m) => m.dependencies.clone(),
+            ModuleMemo::ResolvedTraitImpls(m) => m.dependencies.clone(),
+        }
+    }
+
+    fn revisions(&self) -> (Revision, Revision) {
+        match self {
+            ModuleMemo::ParsedModule(m) => (m.verified_at, m.changed_at),
+            ModuleMemo::TypeCheckedModule(m) => (m.verified_at, m.changed_at),
+            ModuleMemo::ResolvedTraitImpls(m) => (m.verified_at, m.changed_at),
+        }
+    }
+
+    fn set_verified_at(&mut self, revision: Revision) {
+        match self {
+            ModuleMemo::ParsedModule(m) => m.verified_at = revision,
+            ModuleMemo::TypeCheckedModule(m) => m.verified_at = revision,
+            ModuleMemo::ResolvedTraitImpls(m) => m.verified_at = revision,
+        }
+    }
+
+    fn set_revisions(&mut self, verified_at: Revision, changed_at: Revision) {
+        match self {
+            ModuleMemo::ParsedModule(m) => {
+                m.verified_at = verified_at;
+                m.changed_at = changed_at;
+            }
+            ModuleMemo::TypeCheckedModule(m) => {
+                m.verified_at = verified_at;
+                m.changed_at = changed_at;
+            }
+            ModuleMemo::ResolvedTraitImpls(m) => {
+                m.verified_at = verified_at;
+                m.changed_at = changed_at;
+            }
+        }
+    }
+}
+
+macro_rules! impl_memo_slot {
+    ($ty:ty, $variant:ident) => {
+        impl MemoSlot<$ty> for ModuleMemo {
+            fn get(&self) -> Option<&Memo<$ty>> {
+                match self {
+                    ModuleMemo::$variant(m) => Some(m),
+                    _ => None,
+                }
+            }
+
+            fn get_mut(&mut self) -> Option<&mut Memo<$ty>> {
+                match self {
+                    ModuleMemo::$variant(m) => Some(m),
+                    _ => None,
+                }
+            }
+
+            fn new(
+                value: $ty,
+                verified_at: Revision,
+                changed_at: Revision,
+                dependencies: Vec<QueryKey>,
+            ) -> Self {
+                ModuleMemo::$variant(Memo {
+                    value,
+                    verified_at,
+                    changed_at,
+                    dependencies,
+                })
+            }
+        }
+    };
+}
+
+impl_memo_slot!(crate::language::parsed::ParseModule, ParsedModule);
+impl_memo_slot!(crate::language::ty::TyModule, TypeCheckedModule);
+impl_memo_slot!(Vec<crate::decl_engine::DeclRef>, ResolvedTraitImpls);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine_threading::Engines;
+    use std::sync::atomic::AtomicUsize;
+
+    fn key(module_id: ModuleId) -> QueryKey {
+        (QueryKind::ResolvedTraitImpls, module_id)
+    }
+
+    #[test]
+    fn query_reuses_cached_value_without_recomputing() {
+        let engines = Engines::default();
+        let query_engine = QueryEngine::default();
+        let module_id = ModuleId::new(0);
+        let computations = AtomicUsize::new(0);
+
+        let compute = || {
+            computations.fetch_add(1, Ordering::SeqCst);
+            Vec::new()
+        };
+
+        let first: Vec<crate::decl_engine::DeclRef> =
+            query_engine.query(&engines, key(module_id), compute);
+        let second: Vec<crate::decl_engine::DeclRef> =
+            query_engine.query(&engines, key(module_id), compute);
+
+        assert_eq!(first, second);
+        assert_eq!(
+            computations.load(Ordering::SeqCst),
+            1,
+            "second query() call should have reused the cached value instead of recomputing"
+        );
+    }
+
+    #[test]
+    fn invalidating_an_input_forces_its_dependent_to_recompute() {
+        let engines = Engines::default();
+        let query_engine = QueryEngine::default();
+        let input_module = ModuleId::new(0);
+        let derived_module = ModuleId::new(1);
+        let input_key = key(input_module);
+        let derived_key = key(derived_module);
+        let derived_computations = AtomicUsize::new(0);
+
+        // The input query must be called from *inside* the derived query's `compute` closure --
+        // that nesting is what actually records the dependency edge from `derived` onto `input`.
+        // Calling them back-to-back at the top level wouldn't exercise dependency tracking at all.
+        let run_derived = || {
+            query_engine.query(&engines, derived_key, || {
+                query_engine.query(&engines, input_key, Vec::new);
+                derived_computations.fetch_add(1, Ordering::SeqCst);
+                Vec::new()
+            })
+        };
+
+        let _: Vec<crate::decl_engine::DeclRef> = run_derived();
+        let _: Vec<crate::decl_engine::DeclRef> = run_derived();
+        assert_eq!(
+            derived_computations.load(Ordering::SeqCst),
+            1,
+            "second call should be served from the cache since nothing changed"
+        );
+
+        query_engine.invalidate_input(input_key);
+        let _: Vec<crate::decl_engine::DeclRef> = run_derived();
+        assert_eq!(
+            derived_computations.load(Ordering::SeqCst),
+            2,
+            "invalidating the input dependency must force the dependent query to recompute"
+        );
+    }
+}